@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::time::Instant;
 
@@ -12,7 +12,8 @@ use crate::compiler::Compiler;
 use crate::config::{DevtoolConfig, Mode};
 use crate::generate_chunks::OutputAst;
 use crate::minify::minify_js;
-use crate::module::ModuleAst;
+use crate::module::{ModuleAst, ModuleId};
+use crate::progress::{ProgressStage, StageProgress};
 use crate::update::UpdateResult;
 
 impl Compiler {
@@ -25,16 +26,32 @@ impl Compiler {
             self.tree_shaking();
         }
         let t_tree_shaking = t_tree_shaking.elapsed();
+
         let t_group_chunks = Instant::now();
         self.group_chunk();
         let t_group_chunks = t_group_chunks.elapsed();
 
+        // concatenation groups modules per chunk, so this has to run after
+        // group_chunk() has populated the chunk graph — running it earlier
+        // left chunk_graph.get_chunks() empty and made the whole pass a
+        // silent no-op
+        if matches!(self.context.config.mode, Mode::Production)
+            && self.context.config.concatenate_modules
+        {
+            self.concatenate_modules()?;
+        }
+
         // 为啥单独提前 transform modules？
         // 因为放 chunks 的循环里，一个 module 可能存在于多个 chunk 里，可能会被编译多遍
-        let t_transform_modules = Instant::now();
-        info!("transform all modules");
+        //
+        // transform_all() isn't instrumented per-module (it lives outside
+        // this file), so this is only a coarse stage-boundary event, unlike
+        // GenerateChunks/Minify/AstToCodeAndWrite below which report
+        // incrementally from inside their rayon loops
+        let transform_progress =
+            StageProgress::new(&self.context, ProgressStage::TransformModules, 1);
         self.transform_all()?;
-        let t_transform_modules = t_transform_modules.elapsed();
+        transform_progress.inc();
 
         // ensure output dir exists
         let config = &self.context.config;
@@ -42,19 +59,36 @@ impl Compiler {
             fs::create_dir_all(&config.output.path)?;
         }
 
-        // generate chunks
-        // TODO: 并行
-        let t_generate_chunks = Instant::now();
-        info!("generate chunks");
+        // generate chunks (now rendered in parallel, see generate_chunks.rs;
+        // it reports its own StageProgress, so no separate timing log here)
         let mut chunk_asts = self.generate_chunks_ast()?;
-        let t_generate_chunks = t_generate_chunks.elapsed();
 
-        // minify
-        let t_minify = Instant::now();
-        info!("minify");
+        // load the persistent build cache so unchanged chunks can skip
+        // minify/codegen on this build; transform_all() above still runs
+        // every build, since the cache key is the transformed chunk AST
+        let t_cache = Instant::now();
+        let build_cache = BuildCache::load(config.output.path.join("cache"));
+        let chunk_hashes: HashMap<String, u64> = chunk_asts
+            .iter()
+            .map(|file| (file.path.clone(), self.chunk_content_hash(file)))
+            .collect();
+        let cache_hits: HashSet<String> = chunk_hashes
+            .iter()
+            .filter(|(path, hash)| build_cache.get(path, **hash).is_some())
+            .map(|(path, _)| path.clone())
+            .collect();
+        let t_cache = t_cache.elapsed();
+
+        // minify; the StageProgress below reports its own completion/timing
         if self.context.config.minify {
-            chunk_asts
-                .par_iter_mut()
+            let to_minify: Vec<_> = chunk_asts
+                .iter_mut()
+                .filter(|file| !cache_hits.contains(&file.path))
+                .collect();
+            let progress =
+                StageProgress::new(&self.context, ProgressStage::Minify, to_minify.len());
+            to_minify
+                .into_par_iter()
                 .try_for_each(|file| -> Result<()> {
                     if matches!(self.context.config.mode, Mode::Production) {
                         match &mut file.ast {
@@ -67,52 +101,64 @@ impl Compiler {
                             _ => (),
                         }
                     }
+                    progress.inc();
                     Ok(())
                 })?;
         }
-        let t_minify = t_minify.elapsed();
 
-        // ast to code and sourcemap, then write
-        let t_ast_to_code_and_write = Instant::now();
-        info!("ast to code and write");
-        chunk_asts.par_iter().try_for_each(|file| -> Result<()> {
-            match &file.ast {
-                ModuleAst::Script(ast) => {
-                    // ast to code
-                    let (js_code, js_sourcemap) =
-                        js_ast_to_code(&ast.ast, &self.context, &file.path)?;
-                    // generate code and sourcemap files
-                    let output = &config.output.path.join(&file.path);
-                    fs::write(output, js_code).unwrap();
-                    if matches!(self.context.config.devtool, DevtoolConfig::SourceMap) {
-                        fs::write(format!("{}.map", output.display()), js_sourcemap).unwrap();
-                    }
-                }
-                // TODO: Sourcemap part
-                ModuleAst::Css(ast) => {
-                    // ast to code
-                    let (css_code, _sourcemap) = css_ast_to_code(ast, &self.context);
-                    let output = &config.output.path.join(&file.path);
-                    fs::write(output, css_code).unwrap();
-                }
-                _ => (),
+        // ast to code and sourcemap; StageProgress reports its own timing
+        let ast_to_code_progress = StageProgress::new(
+            &self.context,
+            ProgressStage::AstToCodeAndWrite,
+            chunk_asts.len(),
+        );
+        let rendered: Vec<RenderedChunk> = chunk_asts
+            .par_iter()
+            .map(|file| -> Result<RenderedChunk> {
+                let result = self.render_and_cache_chunk(file, &chunk_hashes, &build_cache);
+                ast_to_code_progress.inc();
+                result
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // assign content-hashed filenames (production only) for chunks *and*
+        // assets before writing anything: chunk code can reference other
+        // chunks (dynamic imports) or assets (e.g. a url() in CSS) by their
+        // logical name, so every hashed name has to be known up front for
+        // those references to be rewritten to what actually lands on disk
+        let mut name_map = Self::chunk_name_map(&rendered, self.context.config.content_hash);
+
+        let assets_info = self.context.assets_info.lock().unwrap().clone();
+        for (k, v) in &assets_info {
+            let asset_path = self.context.root.join(k);
+            if !asset_path.exists() {
+                panic!("asset not found: {}", asset_path.display());
             }
-            Ok(())
-        })?;
-        let t_ast_to_code_and_write = t_ast_to_code_and_write.elapsed();
+            let output_name = if self.context.config.content_hash {
+                let bytes = fs::read(&asset_path)?;
+                hashed_filename(v, &bytes)
+            } else {
+                v.clone()
+            };
+            name_map.insert(v.clone(), output_name);
+        }
+
+        let manifest = self.write_rendered_chunks(&config.output.path, rendered, &name_map)?;
 
         // write assets
         let t_write_assets = Instant::now();
         info!("write assets");
-        let assets_info = &(*self.context.assets_info.lock().unwrap());
-        for (k, v) in assets_info {
-            let asset_path = &self.context.root.join(k);
-            let asset_output_path = &config.output.path.join(v);
-            if asset_path.exists() {
-                fs::copy(asset_path, asset_output_path)?;
-            } else {
-                panic!("asset not found: {}", asset_path.display());
-            }
+        for (k, v) in &assets_info {
+            let asset_path = self.context.root.join(k);
+            let output_name = &name_map[v];
+            let asset_output_path = config.output.path.join(output_name);
+            fs::copy(&asset_path, &asset_output_path)?;
+        }
+        if self.context.config.content_hash {
+            fs::write(
+                config.output.path.join("manifest.json"),
+                serde_json::to_string_pretty(&manifest)?,
+            )?;
         }
         let t_write_assets = t_write_assets.elapsed();
 
@@ -122,18 +168,18 @@ impl Compiler {
         self.copy()?;
         let t_copy = t_copy.elapsed();
 
+        // concatenate modules/transform modules/generate chunks/minify/ast to
+        // code and write each report their own completion + elapsed time
+        // through StageProgress -> LoggingProgressReporter, so they're not
+        // repeated here — this summary only covers the stages that have no
+        // ProgressStage of their own.
         info!("generate done in {}ms", t_generate.elapsed().as_millis());
         info!("  - tree shaking: {}ms", t_tree_shaking.as_millis());
         info!("  - group chunks: {}ms", t_group_chunks.as_millis());
         info!(
-            "  - transform modules: {}ms",
-            t_transform_modules.as_millis()
-        );
-        info!("  - generate chunks: {}ms", t_generate_chunks.as_millis());
-        info!("  - minify: {}ms", t_minify.as_millis());
-        info!(
-            "  - ast to code and write: {}ms",
-            t_ast_to_code_and_write.as_millis()
+            "  - cache lookup: {}ms ({} chunk(s) reused)",
+            t_cache.as_millis(),
+            cache_hits.len()
         );
         info!("  - write assets: {}ms", t_write_assets.as_millis());
         info!("  - copy: {}ms", t_copy.as_millis());
@@ -164,12 +210,25 @@ impl Compiler {
                     // generate code and sourcemap files
                     let output = &config.output.path.join(&file.path);
                     fs::write(output, js_code).unwrap();
-                    if matches!(self.context.config.devtool, DevtoolConfig::SourceMap) {
+                    // an empty map means ast_to_code didn't actually produce
+                    // one (e.g. unsupported ast), so don't write a bogus
+                    // `.map` file just because devtool asked for one
+                    if matches!(self.context.config.devtool, DevtoolConfig::SourceMap)
+                        && !js_sourcemap.is_empty()
+                    {
                         fs::write(format!("{}.map", output.display()), js_sourcemap).unwrap();
                     }
                 }
-                ModuleAst::Css(_ast) => {
-                    // TODO: css chunk
+                ModuleAst::Css(ast) => {
+                    // ast to code
+                    let (css_code, css_sourcemap) = css_ast_to_code(ast, &self.context);
+                    let output = &config.output.path.join(&file.path);
+                    fs::write(output, css_code).unwrap();
+                    if matches!(self.context.config.devtool, DevtoolConfig::SourceMap)
+                        && !css_sourcemap.is_empty()
+                    {
+                        fs::write(format!("{}.map", output.display()), css_sourcemap).unwrap();
+                    }
                 }
                 _ => (),
             }
@@ -324,6 +383,244 @@ impl Compiler {
         Ok(current_full_hash)
     }
 
+    // scope hoisting: merge a chunk's pure-ESM modules into a single shared
+    // scope instead of wrapping each module in its own runtime function
+    fn concatenate_modules(&self) -> Result<()> {
+        // build every chunk's group while the graphs are locked for reading,
+        // then drop both guards before hoisting mutates module ASTs —
+        // hoist() needs a write lock on the module graph, and holding these
+        // read guards across that call would deadlock
+        let groups: Vec<ConcatenateGroup> = {
+            let chunk_graph = self.context.chunk_graph.read().unwrap();
+            let module_graph = self.context.module_graph.read().unwrap();
+
+            chunk_graph
+                .get_chunks()
+                .iter()
+                .filter_map(|chunk| {
+                    let entry = chunk.root_module.clone();
+                    let mut group = ConcatenateGroup::new(entry.clone());
+
+                    // pull in statically-imported deps that are only reachable
+                    // from within this group, so inlining them can't change
+                    // ordering or visibility for other chunks
+                    let mut queue = vec![entry];
+                    while let Some(module_id) = queue.pop() {
+                        for dep_id in module_graph.get_dependencies_ids(&module_id) {
+                            if group.members.contains(&dep_id) {
+                                continue;
+                            }
+                            if !Self::is_concatenatable(&module_graph, &dep_id) {
+                                continue;
+                            }
+                            if module_graph.is_module_referenced_outside(&dep_id, &group.members) {
+                                continue;
+                            }
+                            group.members.insert(dep_id.clone());
+                            queue.push(dep_id);
+                        }
+                    }
+
+                    (group.members.len() > 1).then_some(group)
+                })
+                .collect()
+        };
+
+        let progress = StageProgress::new(
+            &self.context,
+            ProgressStage::ConcatenateModules,
+            groups.len(),
+        );
+        for group in groups {
+            group.hoist(&self.context)?;
+            progress.inc();
+        }
+
+        Ok(())
+    }
+
+    fn render_and_cache_chunk(
+        &self,
+        file: &OutputAst,
+        chunk_hashes: &HashMap<String, u64>,
+        build_cache: &BuildCache,
+    ) -> Result<RenderedChunk> {
+        // module/config didn't change since the last build: reuse the
+        // previously emitted bytes straight from the cache, like linking a
+        // prebuilt `.o` file instead of recompiling it
+        if let Some(cached) = build_cache.get(&file.path, chunk_hashes[&file.path]) {
+            return Ok(RenderedChunk {
+                logical_name: file.path.clone(),
+                code: cached.code.clone(),
+                sourcemap: cached.sourcemap.clone(),
+            });
+        }
+
+        match &file.ast {
+            ModuleAst::Script(ast) => {
+                let (js_code, js_sourcemap) = js_ast_to_code(&ast.ast, &self.context, &file.path)?;
+                // an empty map means ast_to_code didn't actually produce one;
+                // don't cache/emit a bogus `.map` just because devtool asked
+                let sourcemap = (matches!(self.context.config.devtool, DevtoolConfig::SourceMap)
+                    && !js_sourcemap.is_empty())
+                .then(|| js_sourcemap.into_bytes());
+                let code = js_code.into_bytes();
+                build_cache.put(
+                    &file.path,
+                    &CachedEmission {
+                        content_hash: chunk_hashes[&file.path],
+                        code: code.clone(),
+                        sourcemap: sourcemap.clone(),
+                    },
+                )?;
+                Ok(RenderedChunk {
+                    logical_name: file.path.clone(),
+                    code,
+                    sourcemap,
+                })
+            }
+            ModuleAst::Css(ast) => {
+                let (css_code, css_sourcemap) = css_ast_to_code(ast, &self.context);
+                let sourcemap = (matches!(self.context.config.devtool, DevtoolConfig::SourceMap)
+                    && !css_sourcemap.is_empty())
+                .then(|| css_sourcemap.into_bytes());
+                let code = css_code.into_bytes();
+                build_cache.put(
+                    &file.path,
+                    &CachedEmission {
+                        content_hash: chunk_hashes[&file.path],
+                        code: code.clone(),
+                        sourcemap: sourcemap.clone(),
+                    },
+                )?;
+                Ok(RenderedChunk {
+                    logical_name: file.path.clone(),
+                    code,
+                    sourcemap,
+                })
+            }
+            _ => Ok(RenderedChunk {
+                logical_name: file.path.clone(),
+                code: Vec::new(),
+                sourcemap: None,
+            }),
+        }
+    }
+
+    // `{name}.{contenthash}.{ext}` filenames derived from each chunk's own
+    // emitted bytes (not the whole-build `full_hash`); the asset hashes in
+    // `name_map` are folded in by the caller so cross-chunk *and*
+    // cross-asset references can be rewritten in the same pass
+    fn chunk_name_map(
+        rendered: &[RenderedChunk],
+        content_hash_enabled: bool,
+    ) -> HashMap<String, String> {
+        rendered
+            .iter()
+            .map(|chunk| {
+                let output_name = if content_hash_enabled {
+                    hashed_filename(&chunk.logical_name, &chunk.code)
+                } else {
+                    chunk.logical_name.clone()
+                };
+                (chunk.logical_name.clone(), output_name)
+            })
+            .collect()
+    }
+
+    // rewrites cross-chunk/cross-asset references to their hashed names,
+    // writes the files, and returns the logical-name -> hashed-name
+    // manifest entries
+    fn write_rendered_chunks(
+        &self,
+        output_dir: &std::path::Path,
+        rendered: Vec<RenderedChunk>,
+        name_map: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        let content_hash_enabled = self.context.config.content_hash;
+
+        for chunk in &rendered {
+            if chunk.code.is_empty() {
+                continue;
+            }
+            let output_name = &name_map[&chunk.logical_name];
+
+            let code = if content_hash_enabled {
+                rewrite_cross_chunk_references(&chunk.code, name_map)
+            } else {
+                chunk.code.clone()
+            };
+
+            let output = output_dir.join(output_name);
+            fs::write(&output, &code).unwrap();
+            if let Some(sourcemap) = &chunk.sourcemap {
+                fs::write(format!("{}.map", output.display()), sourcemap).unwrap();
+            }
+        }
+
+        Ok(if content_hash_enabled {
+            name_map.clone()
+        } else {
+            HashMap::new()
+        })
+    }
+
+    // stable hash of the chunk's finalized, post-generate_chunks_ast() AST,
+    // plus the bits of config that affect how it's emitted. Hashing each
+    // member module's raw source_hash() (the old approach) missed anything
+    // that mutates a module's AST in place without touching its own source
+    // text — tree-shaking a re-export because a *different* module stopped
+    // importing it, or a concatenate_modules scope-hoist rename — so a
+    // warm build could serve stale bytes for a chunk whose finalized
+    // content had actually changed. Hashing `file.ast` itself (the AST
+    // generate_chunks_ast() already built, before ast_to_code) picks up all
+    // of that without paying for a second codegen pass just to cache-key it.
+    fn chunk_content_hash(&self, file: &OutputAst) -> u64 {
+        let mut hash = fnv1a(file.path.as_bytes());
+        hash = combine_hash(hash, fnv1a(format!("{:?}", file.ast).as_bytes()));
+        hash = combine_hash(hash, self.context.config.minify as u64);
+        hash = combine_hash(
+            hash,
+            matches!(self.context.config.mode, Mode::Production) as u64,
+        );
+        // devtool and content_hash both change the bytes render_and_cache_chunk
+        // emits (whether a sourcemap is produced, what filename it hashes to)
+        // without changing the ast, so they have to be folded in too or a
+        // warm build would serve cached bytes built under a different
+        // sourcemap/filename setting
+        hash = combine_hash(
+            hash,
+            matches!(self.context.config.devtool, DevtoolConfig::SourceMap) as u64,
+        );
+        hash = combine_hash(hash, self.context.config.content_hash as u64);
+        // concatenate_modules changes how the ast itself is shaped (which is
+        // already reflected above), but fold the flag in directly too so
+        // flipping it can never coincide with an unrelated ast that happens
+        // to format the same way
+        hash = combine_hash(hash, self.context.config.concatenate_modules as u64);
+        hash
+    }
+
+    // a dependency is only safe to inline into its importer's chunk if
+    // reordering it (ordered_members walks dependencies before dependents)
+    // can't move an observable top-level side effect relative to the entry
+    // module — pure ESM re-exports/helpers are fine, but a module whose
+    // top-level code does something observable (mutates a global, runs a
+    // polyfill, logs, etc.) has to keep executing in its original position
+    fn is_concatenatable(
+        module_graph: &crate::module_graph::ModuleGraph,
+        module_id: &ModuleId,
+    ) -> bool {
+        module_graph
+            .get_module(module_id)
+            .map(|m| {
+                m.is_esm()
+                    && !m.info.as_ref().is_some_and(|i| i.is_dynamic_entry)
+                    && !m.info.as_ref().is_some_and(|i| i.has_side_effects)
+            })
+            .unwrap_or(false)
+    }
+
     pub fn write_to_dist<P: AsRef<std::path::Path>, C: AsRef<[u8]>>(
         &self,
         filename: P,
@@ -335,6 +632,249 @@ impl Compiler {
     }
 }
 
+// a chunk's code/sourcemap after ast-to-code, before content-hashed naming
+// has been assigned
+struct RenderedChunk {
+    logical_name: String,
+    code: Vec<u8>,
+    sourcemap: Option<Vec<u8>>,
+}
+
+// `{name}.{contenthash}.{ext}` where the hash comes from the final emitted
+// bytes, so a chunk's URL only changes across deploys when its own content
+// (not an unrelated sibling chunk) actually changed
+fn hashed_filename(logical_name: &str, bytes: &[u8]) -> String {
+    let hash = format!("{:x}", fnv1a(bytes))
+        .chars()
+        .take(8)
+        .collect::<String>();
+    match logical_name.rsplit_once('.') {
+        Some((left, ext)) => format!("{left}.{hash}.{ext}"),
+        None => format!("{logical_name}.{hash}"),
+    }
+}
+
+// cross-chunk references (e.g. a dynamic `import()` of another chunk, or an
+// asset url() baked into CSS) are emitted as either a quoted string literal,
+// an unquoted CSS `url(...)`, or a backtick template with no interpolation
+// holding the logical name; once every chunk/asset has its hashed name
+// assigned, swap those for the hashed ones. Rewriting only ever touches the
+// *exact* contents of one of those three forms, never a raw substring of
+// the code — a blind `str::replace` would also corrupt a name that happens
+// to be a substring of another (e.g. "app.js" inside "vendor.app.js") and
+// would be unstable across builds since HashMap iteration order isn't
+// deterministic. A template literal that *does* interpolate (`` `${id}.js` ``)
+// is left untouched: its value isn't known until the runtime evaluates it,
+// so no static pass — this one or an AST-based one — can rewrite it; the
+// runtime has to resolve those through a literal-keyed map instead.
+fn rewrite_cross_chunk_references(code: &[u8], name_map: &HashMap<String, String>) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(code) else {
+        return code.to_vec();
+    };
+    let chars: Vec<char> = text.chars().collect();
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+
+        if ch == '"' || ch == '\'' {
+            let (literal, end, closed) = scan_delimited(&chars, i + 1, ch);
+            out.push(ch);
+            out.push_str(name_map.get(literal.as_str()).map(String::as_str).unwrap_or(&literal));
+            if closed {
+                out.push(ch);
+            }
+            i = end;
+            continue;
+        }
+
+        if ch == '`' {
+            let (literal, end, closed, dynamic) = scan_template(&chars, i + 1);
+            out.push('`');
+            if dynamic {
+                out.push_str(&literal);
+            } else {
+                out.push_str(name_map.get(literal.as_str()).map(String::as_str).unwrap_or(&literal));
+            }
+            if closed {
+                out.push('`');
+            }
+            i = end;
+            continue;
+        }
+
+        if is_url_call(&chars, i) {
+            if let Some(close) = find_char(&chars, i + 4, ')') {
+                let inner: String = chars[i + 4..close].iter().collect();
+                let trimmed = inner.trim();
+                // an already-quoted url("...") is handled by the quote
+                // branch above on its next iteration, not here
+                if !trimmed.starts_with('"') && !trimmed.starts_with('\'') {
+                    let leading_ws = &inner[..inner.len() - inner.trim_start().len()];
+                    let trailing_ws = &inner[inner.trim_end().len()..];
+                    out.push_str("url(");
+                    out.push_str(leading_ws);
+                    out.push_str(name_map.get(trimmed).map(String::as_str).unwrap_or(trimmed));
+                    out.push_str(trailing_ws);
+                    out.push(')');
+                    i = close + 1;
+                    continue;
+                }
+            }
+        }
+
+        out.push(ch);
+        i += 1;
+    }
+    out.into_bytes()
+}
+
+// scans a quoted literal starting right after the opening quote, honoring
+// backslash escapes so an escaped quote (`"a\"b"`) doesn't get mistaken for
+// the terminator — that previously truncated the literal early and left the
+// rest of the original text unescaped in the output, corrupting it. Returns
+// the raw (still-escaped) contents, the index right after the closing quote
+// (or end-of-input if unterminated), and whether it was actually closed.
+fn scan_delimited(chars: &[char], mut i: usize, quote: char) -> (String, usize, bool) {
+    let mut literal = String::new();
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && i + 1 < chars.len() {
+            literal.push(c);
+            literal.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if c == quote {
+            return (literal, i + 1, true);
+        }
+        literal.push(c);
+        i += 1;
+    }
+    (literal, i, false)
+}
+
+// same as scan_delimited but for a backtick template, additionally tracking
+// whether a `${` interpolation was seen anywhere inside it
+fn scan_template(chars: &[char], mut i: usize) -> (String, usize, bool, bool) {
+    let mut literal = String::new();
+    let mut dynamic = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && i + 1 < chars.len() {
+            literal.push(c);
+            literal.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if c == '$' && i + 1 < chars.len() && chars[i + 1] == '{' {
+            dynamic = true;
+        }
+        if c == '`' {
+            return (literal, i + 1, true, dynamic);
+        }
+        literal.push(c);
+        i += 1;
+    }
+    (literal, i, false, dynamic)
+}
+
+fn is_url_call(chars: &[char], i: usize) -> bool {
+    let prev_is_ident = i > 0 && is_ident_char(chars[i - 1]);
+    if prev_is_ident || i + 4 > chars.len() {
+        return false;
+    }
+    chars[i..i + 4]
+        .iter()
+        .collect::<String>()
+        .eq_ignore_ascii_case("url(")
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn find_char(chars: &[char], start: usize, target: char) -> Option<usize> {
+    chars[start..].iter().position(|&c| c == target).map(|p| p + start)
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes
+        .iter()
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ (*byte as u64)).wrapping_mul(PRIME))
+}
+
+fn combine_hash(a: u64, b: u64) -> u64 {
+    fnv1a(&[a.to_le_bytes(), b.to_le_bytes()].concat())
+}
+
+// on-disk record of a previously emitted chunk, persisted with rkyv so a
+// warm build can skip straight to `fs::write` instead of re-running
+// minify/codegen for a chunk whose content hash didn't change (modules are
+// still transformed on every build)
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+struct CachedEmission {
+    content_hash: u64,
+    code: Vec<u8>,
+    sourcemap: Option<Vec<u8>>,
+}
+
+struct BuildCache {
+    dir: std::path::PathBuf,
+    entries: HashMap<String, CachedEmission>,
+}
+
+impl BuildCache {
+    fn load(dir: std::path::PathBuf) -> Self {
+        let mut entries = HashMap::new();
+
+        if let Ok(read_dir) = fs::read_dir(&dir) {
+            for entry in read_dir.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("rkyv") {
+                    continue;
+                }
+                let Some(key) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                let Ok(bytes) = fs::read(&path) else {
+                    continue;
+                };
+                // a corrupt or version-mismatched cache entry is discarded
+                // and rebuilt rather than trusted
+                let Ok(archived) = rkyv::check_archived_root::<CachedEmission>(&bytes) else {
+                    continue;
+                };
+                let Ok(cached) = archived.deserialize(&mut rkyv::Infallible) else {
+                    continue;
+                };
+                entries.insert(key.to_string(), cached);
+            }
+        }
+
+        Self { dir, entries }
+    }
+
+    fn get(&self, key: &str, content_hash: u64) -> Option<&CachedEmission> {
+        let cache_key = key.replace(['/', '\\'], "_");
+        self.entries
+            .get(&cache_key)
+            .filter(|cached| cached.content_hash == content_hash)
+    }
+
+    fn put(&self, key: &str, record: &CachedEmission) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let bytes = rkyv::to_bytes::<_, 4096>(record)?;
+        let cache_key = key.replace(['/', '\\'], "_");
+        fs::write(self.dir.join(format!("{cache_key}.rkyv")), bytes.as_slice())?;
+        Ok(())
+    }
+}
+
 fn to_hot_update_chunk_name(chunk_name: &String, hash: u64) -> String {
     match chunk_name.rsplit_once('.') {
         None => {
@@ -346,6 +886,115 @@ fn to_hot_update_chunk_name(chunk_name: &String, hash: u64) -> String {
     }
 }
 
+// a set of modules that will be concatenated into a single scope, rooted at
+// the module that is reached first when walking the chunk's entry
+struct ConcatenateGroup {
+    root: ModuleId,
+    members: HashSet<ModuleId>,
+}
+
+impl ConcatenateGroup {
+    fn new(root: ModuleId) -> Self {
+        let mut members = HashSet::new();
+        members.insert(root.clone());
+        Self { root, members }
+    }
+
+    // hoist every member's top-level declarations into the root module's
+    // scope, renaming colliding bindings and rewriting import references to
+    // point directly at the hoisted bindings; the chunk's entry module is
+    // the only member left unwrapped, at the outermost scope
+    fn hoist(&self, context: &crate::compiler::Context) -> Result<()> {
+        // the rename map only needs read access to the module graph; build
+        // it and drop the guard before touching rewrite_module_for_concatenation,
+        // which needs to mutate module ASTs and so takes its own write lock
+        let rename_map: HashMap<ModuleId, HashMap<String, String>> = {
+            let module_graph = context.module_graph.read().unwrap();
+            let mut taken_names: HashSet<String> = HashSet::new();
+            // bindings are renamed in group order so earlier modules "win"
+            // the unqualified name and later collisions get a numeric suffix
+            let mut rename_map = HashMap::new();
+
+            for module_id in self.ordered_members(&module_graph) {
+                let Some(module) = module_graph.get_module(&module_id) else {
+                    continue;
+                };
+                let mut renames = HashMap::new();
+                for binding in module.top_level_bindings() {
+                    let unique = Self::dedupe_name(&binding, &mut taken_names);
+                    if unique != binding {
+                        renames.insert(binding, unique);
+                    }
+                }
+                rename_map.insert(module_id, renames);
+            }
+            rename_map
+        };
+
+        for module_id in &self.members {
+            let is_root = *module_id == self.root;
+            // the entry module of the chunk is emitted at the outermost
+            // scope, not wrapped in an IIFE, so its top-level side effects
+            // and exports run as if it were never bundled
+            context.rewrite_module_for_concatenation(
+                module_id,
+                rename_map.get(module_id).cloned().unwrap_or_default(),
+                !is_root,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn dedupe_name(name: &str, taken: &mut HashSet<String>) -> String {
+        if taken.insert(name.to_string()) {
+            return name.to_string();
+        }
+        let mut i = 1;
+        loop {
+            let candidate = format!("{name}${i}");
+            if taken.insert(candidate.clone()) {
+                return candidate;
+            }
+            i += 1;
+        }
+    }
+
+    // walk dependencies first so a module's bindings are hoisted before the
+    // modules that import them, matching ESM execution order
+    fn ordered_members(&self, module_graph: &crate::module_graph::ModuleGraph) -> Vec<ModuleId> {
+        let mut visited = HashSet::new();
+        let mut ordered = Vec::new();
+
+        fn visit(
+            module_id: &ModuleId,
+            module_graph: &crate::module_graph::ModuleGraph,
+            members: &HashSet<ModuleId>,
+            visited: &mut HashSet<ModuleId>,
+            ordered: &mut Vec<ModuleId>,
+        ) {
+            if !visited.insert(module_id.clone()) {
+                return;
+            }
+            for dep_id in module_graph.get_dependencies_ids(module_id) {
+                if members.contains(&dep_id) {
+                    visit(&dep_id, module_graph, members, visited, ordered);
+                }
+            }
+            ordered.push(module_id.clone());
+        }
+
+        visit(
+            &self.root,
+            module_graph,
+            &self.members,
+            &mut visited,
+            &mut ordered,
+        );
+        ordered
+    }
+}
+
 #[derive(Serialize)]
 struct HotUpdateManifest {
     #[serde(rename(serialize = "c"))]
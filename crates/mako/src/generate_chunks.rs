@@ -0,0 +1,194 @@
+use anyhow::Result;
+use rayon::prelude::*;
+
+use crate::chunk::{Chunk, ChunkType};
+use crate::compiler::Compiler;
+use crate::module::{Module, ModuleAst, ModuleId};
+use crate::progress::{ProgressStage, StageProgress};
+
+pub struct OutputAst {
+    pub path: String,
+    pub ast: ModuleAst,
+}
+
+// a read-only snapshot of everything a single chunk needs to render, owned
+// rather than borrowed so the module_graph/chunk_graph read guards can be
+// dropped before the parallel fan-out below
+struct ChunksAstSnapshot {
+    modules: std::collections::HashMap<ModuleId, Module>,
+    // a module's statically-imported dependency ids, captured once while the
+    // module graph lock is held so CSS import ordering doesn't need to
+    // re-acquire it per chunk
+    dependencies: std::collections::HashMap<ModuleId, Vec<ModuleId>>,
+}
+
+impl ChunksAstSnapshot {
+    fn get_module(&self, module_id: &ModuleId) -> Option<&Module> {
+        self.modules.get(module_id)
+    }
+
+    fn get_dependencies(&self, module_id: &ModuleId) -> Vec<ModuleId> {
+        self.dependencies.get(module_id).cloned().unwrap_or_default()
+    }
+}
+
+impl Compiler {
+    pub fn generate_chunks_ast(&self) -> Result<Vec<OutputAst>> {
+        // clone everything workers need and drop both read guards before
+        // the fan-out. render_chunk_ast calls into
+        // runtime::wrap_modules_in_chunk, which lives outside this file, so
+        // there's no way to prove from here that it (or anything it calls)
+        // never needs its own module_graph/chunk_graph lock. std::sync::
+        // RwLock isn't read-reentrant, so holding these guards across that
+        // call would risk a deadlock the moment it needs a write lock while
+        // a writer is queued behind ours.
+        let (snapshot, chunks) = {
+            let chunk_graph = self.context.chunk_graph.read().unwrap();
+            let module_graph = self.context.module_graph.read().unwrap();
+
+            let snapshot = ChunksAstSnapshot {
+                modules: module_graph
+                    .modules()
+                    .map(|(module_id, module)| (module_id.clone(), module.clone()))
+                    .collect(),
+                dependencies: module_graph
+                    .modules()
+                    .map(|(module_id, _)| {
+                        (
+                            module_id.clone(),
+                            module_graph.get_dependencies_ids(module_id),
+                        )
+                    })
+                    .collect(),
+            };
+            let chunks: Vec<Chunk> = chunk_graph.get_chunks().iter().cloned().collect();
+            (snapshot, chunks)
+        };
+
+        let progress = StageProgress::new(&self.context, ProgressStage::GenerateChunks, chunks.len());
+
+        chunks
+            .par_iter()
+            .map(|chunk| {
+                let result = self.render_chunk_ast(chunk, &snapshot);
+                progress.inc();
+                result
+            })
+            .collect()
+    }
+
+    fn render_chunk_ast(
+        &self,
+        chunk: &Chunk,
+        snapshot: &ChunksAstSnapshot,
+    ) -> Result<OutputAst> {
+        let modules: Vec<&Module> = chunk
+            .get_modules()
+            .iter()
+            .filter_map(|module_id| snapshot.get_module(module_id))
+            .collect();
+
+        let ast = match chunk.chunk_type {
+            ChunkType::Runtime | ChunkType::Entry(_, _) | ChunkType::Async => {
+                self.render_js_chunk(chunk, &modules)?
+            }
+            ChunkType::Css => self.render_css_chunk(chunk, &modules, snapshot)?,
+        };
+
+        Ok(OutputAst {
+            path: chunk.filename(),
+            ast,
+        })
+    }
+
+    fn render_js_chunk(&self, chunk: &Chunk, modules: &[&Module]) -> Result<ModuleAst> {
+        crate::runtime::wrap_modules_in_chunk(self, chunk, modules)
+    }
+
+    // CSS has no runtime wrapper to order execution at load time, so import
+    // order has to be preserved in the concatenated output itself: a module
+    // that is only reached via `@import`/imported styles must be emitted
+    // before the module that imports it, the same order the browser would
+    // have applied them in if they were separate <link> tags. Dependency
+    // edges come from `snapshot`, not a fresh lock, so workers stay lock-free.
+    //
+    // Each rule keeps the `Span` it was parsed with, which is what lets
+    // css_ast_to_code's sourcemap output resolve a rule in the merged
+    // output back to its real source file/offset — only the synthetic
+    // root Stylesheet node below is a dummy span, since it never existed
+    // in any source file and codegen doesn't map against it. That depends
+    // on every module sharing one SourceMap registered at parse time,
+    // which is set up in crate::ast, outside this file.
+    fn render_css_chunk(
+        &self,
+        chunk: &Chunk,
+        modules: &[&Module],
+        snapshot: &ChunksAstSnapshot,
+    ) -> Result<ModuleAst> {
+        let ordered = Self::order_css_modules(chunk, modules, snapshot);
+        let stylesheets = ordered
+            .iter()
+            .filter_map(|module| module.info.as_ref()?.ast.as_css())
+            .flat_map(|stylesheet| stylesheet.rules.clone())
+            // the imported module's rules are already inlined directly
+            // above in `ordered`, so keeping the `@import` at-rule
+            // itself would make the browser double-fetch that stylesheet
+            .filter(|rule| !is_import_rule(rule))
+            .collect();
+
+        Ok(ModuleAst::Css(swc_css_ast::Stylesheet {
+            span: Default::default(),
+            rules: stylesheets,
+        }))
+    }
+
+    fn order_css_modules<'a>(
+        chunk: &Chunk,
+        modules: &[&'a Module],
+        snapshot: &ChunksAstSnapshot,
+    ) -> Vec<&'a Module> {
+        let by_id: std::collections::HashMap<&ModuleId, &Module> =
+            modules.iter().map(|m| (&m.id, *m)).collect();
+        let mut visited = std::collections::HashSet::new();
+        let mut ordered = Vec::with_capacity(modules.len());
+
+        fn visit<'a>(
+            module_id: &ModuleId,
+            by_id: &std::collections::HashMap<&ModuleId, &'a Module>,
+            snapshot: &ChunksAstSnapshot,
+            visited: &mut std::collections::HashSet<ModuleId>,
+            ordered: &mut Vec<&'a Module>,
+        ) {
+            if !visited.insert(module_id.clone()) {
+                return;
+            }
+            // imported/`@import`-ed styles are walked first so their rules
+            // land before the importing module's own rules
+            for dep_id in snapshot.get_dependencies(module_id) {
+                if by_id.contains_key(&dep_id) {
+                    visit(&dep_id, by_id, snapshot, visited, ordered);
+                }
+            }
+            if let Some(module) = by_id.get(module_id) {
+                ordered.push(*module);
+            }
+        }
+
+        for module_id in chunk.get_modules() {
+            visit(module_id, &by_id, snapshot, &mut visited, &mut ordered);
+        }
+
+        ordered
+    }
+}
+
+fn is_import_rule(rule: &swc_css_ast::Rule) -> bool {
+    matches!(
+        rule,
+        swc_css_ast::Rule::AtRule(at_rule)
+            if matches!(
+                &at_rule.name,
+                swc_css_ast::AtRuleName::Ident(ident) if ident.value.eq_ignore_ascii_case("import")
+            )
+    )
+}
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+use crate::compiler::Context;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStage {
+    TransformModules,
+    ConcatenateModules,
+    GenerateChunks,
+    Minify,
+    AstToCodeAndWrite,
+}
+
+impl ProgressStage {
+    fn label(&self) -> &'static str {
+        match self {
+            ProgressStage::TransformModules => "transform modules",
+            ProgressStage::ConcatenateModules => "concatenate modules",
+            ProgressStage::GenerateChunks => "generate chunks",
+            ProgressStage::Minify => "minify",
+            ProgressStage::AstToCodeAndWrite => "ast to code and write",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressEvent {
+    pub stage: ProgressStage,
+    pub done: usize,
+    pub total: usize,
+    // only set on the event that completes the stage (done == total), so a
+    // reporter can log a single "stage: N/N done in Xms" line instead of
+    // generate() keeping its own parallel Instant/elapsed bookkeeping and
+    // logging the same stage a second time
+    pub elapsed: Option<Duration>,
+}
+
+// an embedding CLI or dev server implements this to render a live progress
+// bar; `on_progress` is called once per completed item (from inside the
+// rayon loops for chunk generation, minify and ast-to-code), not only at
+// stage boundaries, so long stages are observable while they're running
+// rather than appearing to hang between log lines
+pub trait ProgressReporter: Send + Sync {
+    fn on_progress(&self, event: ProgressEvent);
+}
+
+// the reporter installed when no embedder configured one: it's just the
+// existing `info!` timing logs, now driven off the same event stream rather
+// than being a second, hardcoded logging path alongside it
+pub struct LoggingProgressReporter;
+
+impl ProgressReporter for LoggingProgressReporter {
+    fn on_progress(&self, event: ProgressEvent) {
+        if event.done == event.total {
+            match event.elapsed {
+                Some(elapsed) => info!(
+                    "{}: {}/{} done in {}ms",
+                    event.stage.label(),
+                    event.done,
+                    event.total,
+                    elapsed.as_millis()
+                ),
+                None => info!("{}: {}/{} done", event.stage.label(), event.done, event.total),
+            }
+        }
+    }
+}
+
+// per-stage counter handed to a rayon loop; each worker calls `inc()` as it
+// finishes an item, and the counter fans that out to whatever reporter is
+// installed on the context. Also tracks the stage's own start time so the
+// completion event can carry how long the whole stage took, instead of
+// generate() timing it separately with its own Instant/elapsed and logging
+// it again.
+pub struct StageProgress<'a> {
+    context: &'a Context,
+    stage: ProgressStage,
+    total: usize,
+    done: AtomicUsize,
+    start: Instant,
+}
+
+impl<'a> StageProgress<'a> {
+    pub fn new(context: &'a Context, stage: ProgressStage, total: usize) -> Self {
+        // a stage with nothing to do never gets an inc(), so report its
+        // completion right away
+        if total == 0 {
+            context.report_progress(ProgressEvent {
+                stage,
+                done: 0,
+                total: 0,
+                elapsed: Some(Duration::ZERO),
+            });
+        }
+        Self {
+            context,
+            stage,
+            total,
+            done: AtomicUsize::new(0),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn inc(&self) {
+        let done = self.done.fetch_add(1, Ordering::SeqCst) + 1;
+        self.context.report_progress(ProgressEvent {
+            stage: self.stage,
+            done,
+            total: self.total,
+            elapsed: (done == self.total).then(|| self.start.elapsed()),
+        });
+    }
+}
+
+impl Context {
+    pub fn report_progress(&self, event: ProgressEvent) {
+        match self.progress_reporter.as_ref() {
+            Some(reporter) => reporter.on_progress(event),
+            None => LoggingProgressReporter.on_progress(event),
+        }
+    }
+}